@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,6 +37,7 @@ impl TaxBracket {
 pub struct Constants {
     pub tfsa_contribution_limit: f64,
     pub rrsp_contribution_upper_limit: f64,
+    pub ympe: f64,
     pub tax_brackets: Vec<TaxBracket>,
 }
 
@@ -42,10 +45,12 @@ impl Constants {
     pub fn new(tax_brackets: Vec<TaxBracket>) -> Self {
         const MAX_RRSP_CONTRIBUTION: i32 = 26500; // 2019 value
         const MAX_TFSA_CONTRIBUTION: i32 = 6000; // 2021 value
+        const YMPE: i32 = 61_600; // 2023 value
 
         Self {
             tfsa_contribution_limit: MAX_TFSA_CONTRIBUTION as f64,
             rrsp_contribution_upper_limit: MAX_RRSP_CONTRIBUTION as f64,
+            ympe: YMPE as f64,
             tax_brackets,
         }
     }
@@ -54,6 +59,7 @@ impl Constants {
         Self {
             tfsa_contribution_limit: self.tfsa_contribution_limit * inflation_rate,
             rrsp_contribution_upper_limit: self.rrsp_contribution_upper_limit * inflation_rate,
+            ympe: self.ympe * inflation_rate,
             tax_brackets: self
                 .tax_brackets
                 .iter()
@@ -73,7 +79,11 @@ pub struct FiscalYear {
     pub tfsa_assets: f64,
     pub unregistered_contribution: f64,
     pub unregistered_assets: f64,
+    pub unregistered_acb: f64,
     pub cost_of_living: f64,
+    pub ympe_fraction_history: Vec<f64>,
+    pub ympe_history: Vec<f64>,
+    pub holding_balances: BTreeMap<String, f64>,
     pub constants: Constants,
 }
 
@@ -97,6 +107,38 @@ impl FiscalYear {
     pub fn total_contribution(&self) -> f64 {
         self.total_rrsp_contribution() + self.tfsa_contribution + self.unregistered_contribution
     }
+
+    pub fn marginal_tax_rate(&self) -> f64 {
+        marginal_tax_rate(&self.constants.tax_brackets, self.taxable_income())
+    }
+
+    pub fn effective_tax_rate(&self) -> f64 {
+        effective_tax_rate(&self.constants.tax_brackets, self.taxable_income())
+    }
+}
+
+pub fn marginal_tax_rate(tax_brackets: &[TaxBracket], income: f64) -> f64 {
+    // Each bracket ladder's top tier shares the same sentinel upper bound (inflated
+    // alongside income), so treat it as open-ended once income has reached it.
+    let ceiling = tax_brackets.iter().map(|b| b.upper_bound).max().unwrap_or(0);
+
+    tax_brackets
+        .iter()
+        .filter(|b| {
+            income >= b.lower_bound as f64
+                && (income < b.upper_bound as f64 || b.upper_bound == ceiling)
+        })
+        .map(|b| b.rate)
+        .sum()
+}
+
+pub fn effective_tax_rate(tax_brackets: &[TaxBracket], income: f64) -> f64 {
+    if income <= 0.0 {
+        return 0.0;
+    }
+
+    let taxes: f64 = tax_brackets.iter().map(|b| b.compute_tax(income)).sum();
+    taxes / income * 100.0
 }
 
 pub fn net_income(tax_brackets: &[TaxBracket], income: f64, capital_gains: f64) -> f64 {
@@ -133,3 +175,66 @@ pub fn rrsp_contribution_headroom(year: &FiscalYear) -> f64 {
 pub fn return_on_investment(asset: f64, rate_of_return: f64) -> f64 {
     asset * (rate_of_return - 1.0)
 }
+
+pub fn cpp_benefit(
+    ympe_fraction_history: &[f64],
+    ympe_history: &[f64],
+    retirement_age: f64,
+) -> f64 {
+    const GENERAL_DROPOUT_FACTOR: f64 = 0.17;
+    const RETIREMENT_BENEFIT_FRACTION: f64 = 0.25;
+    const STANDARD_RETIREMENT_AGE: f64 = 65.0;
+    const EARLY_ADJUSTMENT_PER_MONTH: f64 = 0.006;
+    const LATE_ADJUSTMENT_PER_MONTH: f64 = 0.007;
+    const AVERAGING_YEARS: usize = 5;
+
+    let working_years = ympe_fraction_history.len() as f64;
+    let dropout_years = GENERAL_DROPOUT_FACTOR * working_years;
+    let earning_history_length = working_years - dropout_years;
+
+    let mut fractions = ympe_fraction_history.to_vec();
+    fractions.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+    let full_years = earning_history_length.floor() as usize;
+    let boundary_weight = earning_history_length - full_years as f64;
+
+    let mut weighted_sum: f64 = fractions.iter().take(full_years).sum();
+    if let Some(boundary_fraction) = fractions.get(full_years) {
+        weighted_sum += boundary_fraction * boundary_weight;
+    }
+
+    let average_fraction = weighted_sum / earning_history_length;
+
+    let recent_years = ympe_history.len().min(AVERAGING_YEARS);
+    let average_max_pensionable_earnings: f64 =
+        ympe_history.iter().rev().take(recent_years).sum::<f64>() / recent_years as f64;
+
+    let annual_benefit =
+        average_fraction * average_max_pensionable_earnings * RETIREMENT_BENEFIT_FRACTION;
+
+    let months_from_standard_age = (retirement_age - STANDARD_RETIREMENT_AGE) * 12.0;
+    let adjustment_factor = if months_from_standard_age < 0.0 {
+        1.0 + months_from_standard_age * EARLY_ADJUSTMENT_PER_MONTH
+    } else {
+        1.0 + months_from_standard_age * LATE_ADJUSTMENT_PER_MONTH
+    };
+
+    annual_benefit * adjustment_factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpp_benefit_matches_hand_computed_max_at_65() {
+        // Always at/above YMPE, flat 61,600 YMPE over a 5-year average, retiring at 65:
+        // average_fraction = 1.0, so benefit = 1.0 * 61_600.0 * 0.25 = 15_400.0, unadjusted.
+        let ympe_fraction_history = vec![1.0; 40];
+        let ympe_history = vec![61_600.0; 40];
+
+        let benefit = cpp_benefit(&ympe_fraction_history, &ympe_history, 65.0);
+
+        assert!((benefit - 15_400.0).abs() < 1e-6);
+    }
+}
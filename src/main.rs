@@ -21,6 +21,9 @@ struct Opt {
 
     #[structopt(short, long, default_value = "config.toml")]
     config_file: String,
+
+    #[structopt(long)]
+    find_crossover: bool,
 }
 
 fn main() -> Result<()> {
@@ -32,7 +35,12 @@ fn main() -> Result<()> {
     let config = toml::from_str(&config_file_content).context("Invalid TOML in config file")?;
 
     let simulation = Simulation::new(&config);
-    output::print(simulation, options.number_of_years, options.base_year);
+
+    if options.find_crossover {
+        output::print_crossover(simulation, options.number_of_years, options.base_year);
+    } else {
+        output::print(simulation, options.number_of_years, options.base_year);
+    }
 
     Ok(())
 }
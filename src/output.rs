@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::io::stdout;
 
 use crate::simulation::{Simulation, SimulationStep};
@@ -19,6 +20,12 @@ struct SimulationOutput {
     #[serde(serialize_with = "format_num", rename = "Net Income")]
     net_income: i32,
 
+    #[serde(rename = "Marginal Tax Rate")]
+    marginal_tax_rate: f64,
+
+    #[serde(rename = "Effective Tax Rate")]
+    effective_tax_rate: f64,
+
     #[serde(serialize_with = "format_num", rename = "Cost of Living")]
     cost_of_living: i32,
 
@@ -49,14 +56,26 @@ struct SimulationOutput {
     #[serde(serialize_with = "format_num", rename = "Unregistered Assets")]
     unregistered_assets: i32,
 
+    #[serde(serialize_with = "format_num", rename = "Unregistered ACB")]
+    unregistered_acb: i32,
+
     #[serde(serialize_with = "format_num", rename = "Total Assets")]
     total_assets: i32,
 
+    #[serde(serialize_with = "format_num", rename = "CPP Benefit")]
+    cpp_benefit: i32,
+
+    #[serde(serialize_with = "format_num", rename = "Realized Capital Gain")]
+    realized_capital_gain: i32,
+
     #[serde(serialize_with = "format_num", rename = "Passive Income")]
     passive_income: i32,
 
     #[serde(serialize_with = "format_num", rename = "Retirement Cost of Living")]
     retirement_cost_of_living: i32,
+
+    #[serde(flatten)]
+    holding_balances: BTreeMap<String, i32>,
 }
 
 impl From<(usize, SimulationStep<'_>)> for SimulationOutput {
@@ -66,6 +85,8 @@ impl From<(usize, SimulationStep<'_>)> for SimulationOutput {
             income: step.fiscal_year.income as i32,
             taxable_income: step.fiscal_year.taxable_income() as i32,
             net_income: step.fiscal_year.net_income() as i32,
+            marginal_tax_rate: step.fiscal_year.marginal_tax_rate(),
+            effective_tax_rate: step.fiscal_year.effective_tax_rate(),
             cost_of_living: step.fiscal_year.cost_of_living as i32,
             personal_rrsp_contribution: step.fiscal_year.personal_rrsp_contribution as i32,
             contribution_to_employer_rrsp: step.fiscal_year.employer_rrsp_contribution as i32,
@@ -76,9 +97,18 @@ impl From<(usize, SimulationStep<'_>)> for SimulationOutput {
             rrsp_assets: step.fiscal_year.rrsp_assets as i32,
             tfsa_assets: step.fiscal_year.tfsa_assets as i32,
             unregistered_assets: step.fiscal_year.unregistered_assets as i32,
+            unregistered_acb: step.remaining_unregistered_acb() as i32,
             total_assets: step.fiscal_year.total_assets() as i32,
+            cpp_benefit: step.cpp_benefit() as i32,
+            realized_capital_gain: step.realized_capital_gain() as i32,
             passive_income: step.passive_income() as i32,
             retirement_cost_of_living: step.retirement_cost_of_living as i32,
+            holding_balances: step
+                .fiscal_year
+                .holding_balances
+                .iter()
+                .map(|(name, balance)| (name.clone(), *balance as i32))
+                .collect(),
         }
     }
 }
@@ -112,3 +142,22 @@ pub fn print(sim: Simulation, number_of_years: usize, base_year: usize) {
         })
         .for_each(|s| writer.serialize(s).unwrap());
 }
+
+pub fn print_crossover(sim: Simulation, number_of_years: usize, base_year: usize) {
+    let crossover = sim
+        .take(number_of_years)
+        .enumerate()
+        .find(|(_, step)| step.passive_income() >= step.retirement_cost_of_living);
+
+    match crossover {
+        Some((year, step)) => println!(
+            "You can retire in year {} with {} in assets.",
+            year + base_year,
+            step.fiscal_year.total_assets() as i32
+        ),
+        None => println!(
+            "Passive income never reaches the retirement cost of living within {} years.",
+            number_of_years
+        ),
+    }
+}
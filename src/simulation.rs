@@ -1,4 +1,5 @@
 use std::cmp::min;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::accounting::{self, FiscalYear};
 use crate::accounting::{Constants, TaxBracket};
@@ -11,6 +12,12 @@ struct InitialValues {
     cost_of_living: i32,
     retirement_cost_of_living: i32,
 
+    #[serde(default = "default_retirement_age")]
+    retirement_age: i32,
+
+    #[serde(default = "default_current_age")]
+    current_age: i32,
+
     #[serde(default)]
     rrsp_contribution_headroom: i32,
 
@@ -30,6 +37,24 @@ struct Rates {
     salary_growth: f64,
     return_on_investment: f64,
 
+    #[serde(default)]
+    inflation_terminal: Option<f64>,
+
+    #[serde(default)]
+    inflation_taper: Option<f64>,
+
+    #[serde(default)]
+    salary_growth_terminal: Option<f64>,
+
+    #[serde(default)]
+    salary_growth_taper: Option<f64>,
+
+    #[serde(default)]
+    return_on_investment_terminal: Option<f64>,
+
+    #[serde(default)]
+    return_on_investment_taper: Option<f64>,
+
     #[serde(default)]
     employer_rrsp_match: f64,
 
@@ -40,6 +65,100 @@ struct Rates {
     withdraw_rate: f64,
 }
 
+impl Rates {
+    fn inflation(&self, year: usize) -> f64 {
+        tapered_rate(
+            self.inflation,
+            self.inflation_terminal,
+            self.inflation_taper,
+            year,
+        )
+    }
+
+    fn salary_growth(&self, year: usize) -> f64 {
+        tapered_rate(
+            self.salary_growth,
+            self.salary_growth_terminal,
+            self.salary_growth_taper,
+            year,
+        )
+    }
+
+    fn return_on_investment(&self, year: usize) -> f64 {
+        tapered_rate(
+            self.return_on_investment,
+            self.return_on_investment_terminal,
+            self.return_on_investment_taper,
+            year,
+        )
+    }
+}
+
+fn tapered_rate(initial: f64, terminal: Option<f64>, taper: Option<f64>, year: usize) -> f64 {
+    match (terminal, taper) {
+        (Some(terminal), Some(taper)) => {
+            f64::max(terminal, initial * (1.0 - taper).powi(year as i32))
+        }
+        _ => initial,
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Holding {
+    name: String,
+    expected_return: f64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Portfolio {
+    holdings: Vec<Holding>,
+    initial_allocation: HashMap<String, f64>,
+
+    #[serde(default)]
+    terminal_allocation: HashMap<String, f64>,
+
+    #[serde(default = "default_glide_path_years")]
+    glide_path_years: usize,
+}
+
+impl Portfolio {
+    fn allocation(&self, year: usize) -> BTreeMap<String, f64> {
+        let progress = if self.terminal_allocation.is_empty() {
+            0.0
+        } else {
+            f64::min(1.0, year as f64 / self.glide_path_years as f64)
+        };
+
+        self.holdings
+            .iter()
+            .map(|holding| {
+                let initial = *self.initial_allocation.get(&holding.name).unwrap_or(&0.0);
+                let terminal = *self
+                    .terminal_allocation
+                    .get(&holding.name)
+                    .unwrap_or(&initial);
+
+                (holding.name.clone(), initial + (terminal - initial) * progress)
+            })
+            .collect()
+    }
+
+    fn blended_return(&self, year: usize) -> f64 {
+        let allocation = self.allocation(year);
+
+        self.holdings
+            .iter()
+            .map(|holding| {
+                allocation.get(&holding.name).copied().unwrap_or(0.0) * holding.expected_return
+            })
+            .sum()
+    }
+}
+
+fn default_glide_path_years() -> usize {
+    20
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     #[serde(flatten)]
@@ -48,6 +167,9 @@ pub struct Config {
     #[serde(flatten)]
     initial_values: InitialValues,
 
+    #[serde(default)]
+    portfolio: Option<Portfolio>,
+
     #[serde(default, alias = "state_tax_brackets")]
     provincial_tax_brackets: Vec<TaxBracket>,
 
@@ -63,6 +185,14 @@ fn default_withdraw_rate() -> f64 {
     0.04
 }
 
+fn default_retirement_age() -> i32 {
+    65
+}
+
+fn default_current_age() -> i32 {
+    30
+}
+
 pub struct Simulation<'a> {
     step: SimulationStep<'a>,
 }
@@ -89,7 +219,11 @@ impl<'a> Iterator for Simulation<'a> {
 pub struct SimulationStep<'a> {
     pub fiscal_year: FiscalYear,
     pub retirement_cost_of_living: f64,
+    retirement_age: i32,
+    current_age: i32,
+    year: usize,
     rates: &'a Rates,
+    portfolio: Option<&'a Portfolio>,
 }
 
 impl<'a> SimulationStep<'a> {
@@ -131,6 +265,17 @@ impl<'a> SimulationStep<'a> {
         let unregistered_assets =
             config.initial_values.unregistered_assets as f64 + unregistered_contribution;
 
+        let unregistered_acb =
+            config.initial_values.unregistered_assets as f64 + unregistered_contribution;
+
+        let ympe_fraction = f64::min(income, constants.ympe) / constants.ympe;
+
+        let holding_balances = holding_balances(
+            config.portfolio.as_ref(),
+            0,
+            rrsp_assets + tfsa_assets + unregistered_assets,
+        );
+
         let year_0 = FiscalYear {
             income: income as f64,
             personal_rrsp_contribution,
@@ -140,24 +285,40 @@ impl<'a> SimulationStep<'a> {
             tfsa_assets,
             unregistered_contribution,
             unregistered_assets,
+            unregistered_acb,
             cost_of_living: config.initial_values.cost_of_living as f64,
+            ympe_fraction_history: vec![ympe_fraction],
+            ympe_history: vec![constants.ympe],
+            holding_balances,
             constants,
         };
 
         SimulationStep {
             fiscal_year: year_0,
             retirement_cost_of_living: config.initial_values.retirement_cost_of_living as f64,
+            retirement_age: config.initial_values.retirement_age,
+            current_age: config.initial_values.current_age,
+            year: 0,
             rates: &config.rates,
+            portfolio: config.portfolio.as_ref(),
         }
     }
 
     fn next(&self) -> Self {
         let rates = self.rates;
         let previous = self;
+        let year = previous.year + 1;
+
+        let inflation = rates.inflation(year);
+        let salary_growth = rates.salary_growth(year);
+        let return_on_investment = match previous.portfolio {
+            Some(portfolio) => portfolio.blended_return(year),
+            None => rates.return_on_investment(year),
+        };
 
         let income = f64::min(
             rates.salary_cap as f64,
-            previous.fiscal_year.income * rates.salary_growth,
+            previous.fiscal_year.income * salary_growth,
         );
 
         let rrsp_contribution_headroom =
@@ -172,14 +333,11 @@ impl<'a> SimulationStep<'a> {
 
         let total_rrsp_contribution = personal_rrsp_contribution + employer_rrsp_contribution;
 
-        let constants = previous
-            .fiscal_year
-            .constants
-            .adjust_for_inflation(rates.inflation);
+        let constants = previous.fiscal_year.constants.adjust_for_inflation(inflation);
 
         let taxable_income = income - personal_rrsp_contribution;
         let net_income = accounting::net_income(&constants.tax_brackets, taxable_income, 0.0);
-        let cost_of_living = previous.fiscal_year.cost_of_living * rates.inflation;
+        let cost_of_living = previous.fiscal_year.cost_of_living * inflation;
 
         let tfsa_contribution =
             accounting::tfsa_contribution(&constants, net_income, cost_of_living);
@@ -198,7 +356,7 @@ impl<'a> SimulationStep<'a> {
             let period_rrsp_contribution = total_rrsp_contribution / NB_PAY_PERIOD as f64;
             let period_unnegistered_contribution = unregistered_contribution / NB_PAY_PERIOD as f64;
 
-            let period_return = nth_root(NB_PAY_PERIOD, rates.return_on_investment);
+            let period_return = nth_root(NB_PAY_PERIOD, return_on_investment);
 
             for _ in 0..NB_PAY_PERIOD {
                 rrsp_assets += accounting::return_on_investment(rrsp_assets, period_return)
@@ -213,6 +371,22 @@ impl<'a> SimulationStep<'a> {
             }
         }
 
+        let unregistered_acb = previous.fiscal_year.unregistered_acb + unregistered_contribution;
+
+        let ympe_fraction = f64::min(income, constants.ympe) / constants.ympe;
+
+        let mut ympe_fraction_history = previous.fiscal_year.ympe_fraction_history.clone();
+        ympe_fraction_history.push(ympe_fraction);
+
+        let mut ympe_history = previous.fiscal_year.ympe_history.clone();
+        ympe_history.push(constants.ympe);
+
+        let holding_balances = holding_balances(
+            previous.portfolio,
+            year,
+            rrsp_assets + tfsa_assets + unregistered_assets,
+        );
+
         let next_year = FiscalYear {
             income,
             personal_rrsp_contribution,
@@ -222,27 +396,83 @@ impl<'a> SimulationStep<'a> {
             tfsa_assets,
             unregistered_contribution,
             unregistered_assets,
+            unregistered_acb,
             cost_of_living,
+            ympe_fraction_history,
+            ympe_history,
+            holding_balances,
             constants,
         };
 
         SimulationStep {
             fiscal_year: next_year,
-            retirement_cost_of_living: previous.retirement_cost_of_living * rates.inflation,
+            retirement_cost_of_living: previous.retirement_cost_of_living * inflation,
+            retirement_age: previous.retirement_age,
+            current_age: previous.current_age,
+            year,
             rates,
+            portfolio: previous.portfolio,
         }
     }
 
+    pub fn cpp_benefit(&self) -> f64 {
+        let current_age = self.current_age + self.year as i32;
+        if current_age < self.retirement_age {
+            return 0.0;
+        }
+
+        let year = &self.fiscal_year;
+
+        accounting::cpp_benefit(
+            &year.ympe_fraction_history,
+            &year.ympe_history,
+            self.retirement_age as f64,
+        )
+    }
+
+    pub fn realized_capital_gain(&self) -> f64 {
+        let year = &self.fiscal_year;
+        let withdraw_rate = self.rates.withdraw_rate;
+
+        (year.unregistered_assets - year.unregistered_acb) * withdraw_rate
+    }
+
+    pub fn remaining_unregistered_acb(&self) -> f64 {
+        let year = &self.fiscal_year;
+        let withdraw_rate = self.rates.withdraw_rate;
+
+        year.unregistered_acb * (1.0 - withdraw_rate)
+    }
+
     pub fn passive_income(&self) -> f64 {
         let year = &self.fiscal_year;
         let withdraw_rate = self.rates.withdraw_rate;
 
+        let acb_withdrawn = year.unregistered_acb * withdraw_rate;
+
         year.tfsa_assets * withdraw_rate
+            + acb_withdrawn
             + accounting::net_income(
                 &year.constants.tax_brackets,
                 year.rrsp_assets * withdraw_rate,
-                year.unregistered_assets * withdraw_rate,
+                self.realized_capital_gain(),
             )
+            + self.cpp_benefit()
+    }
+}
+
+fn holding_balances(
+    portfolio: Option<&Portfolio>,
+    year: usize,
+    total_assets: f64,
+) -> BTreeMap<String, f64> {
+    match portfolio {
+        Some(portfolio) => portfolio
+            .allocation(year)
+            .into_iter()
+            .map(|(name, weight)| (name, total_assets * weight))
+            .collect(),
+        None => BTreeMap::new(),
     }
 }
 